@@ -0,0 +1,441 @@
+//! Adapters that wrap a Search to change how it's carried out, without the caller
+//! having to reimplement the protocol exchange.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::controls::RawControl;
+use crate::exop::ber;
+use crate::result::{LdapResult, SearchResult};
+use crate::search::{ResultEntry, Scope};
+
+/// The normalized parameters of a Search, used by adapters to key per-search state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SearchKey {
+    pub base: String,
+    pub scope: i32,
+    pub filter: String,
+    pub attrs: Vec<String>,
+    pub ctrls: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl SearchKey {
+    pub fn new<S: AsRef<str>>(
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: &[S],
+        ctrls: &[RawControl],
+    ) -> Self {
+        let mut attrs: Vec<String> = attrs.iter().map(|a| a.as_ref().to_string()).collect();
+        attrs.sort();
+        SearchKey {
+            base: base.to_string(),
+            scope: scope as i32,
+            filter: normalize_filter(filter),
+            attrs,
+            ctrls: ctrls
+                .iter()
+                .map(|c| (c.ctype.clone(), c.val.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Normalize a filter string so that searches which differ only in incidental
+/// whitespace or attribute-description case hit the same cache entry.
+///
+/// Only the attribute description of each simple filter component (`attr` in
+/// `(attr=value)`) is whitespace-collapsed and lowercased, since LDAP attribute
+/// descriptions are case-insensitive; the asserted value is passed through
+/// unchanged, since whether two values are equal depends on the attribute's
+/// matching rule, which this adapter has no way to look up.
+fn normalize_filter(filter: &str) -> String {
+    let bytes = filter.as_bytes();
+    let mut out = String::with_capacity(filter.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] as char {
+            c @ ('(' | ')' | '&' | '|' | '!') => {
+                out.push(c);
+                i += 1;
+            }
+            c if c.is_whitespace() => {
+                // Drop structural whitespace between operators/parens.
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && bytes[i] != b')' {
+                    i += 1;
+                }
+                out.push_str(&normalize_filter_leaf(&filter[start..i]));
+            }
+        }
+    }
+    out
+}
+
+/// Normalize a single `attr<op>value` filter component, lowercasing and
+/// whitespace-collapsing `attr` while leaving `value` untouched.
+fn normalize_filter_leaf(term: &str) -> String {
+    for op in [">=", "<=", "~=", ":=", "="] {
+        if let Some(idx) = term.find(op) {
+            let attr = term[..idx].split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+            let value = &term[idx + op.len()..];
+            return format!("{}{}{}", attr, op, value);
+        }
+    }
+    term.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Outcome of an adapter's pre-search hook.
+pub enum AdapterAction {
+    /// Let the Search proceed to the wire as normal.
+    Proceed,
+    /// Short-circuit the Search, serving this result instead.
+    Serve(SearchResult),
+}
+
+/// A Search adapter: a set of hooks that can short-circuit a Search before it's sent,
+/// decide how many wire requests it makes, and/or observe and rewrite its result
+/// once it completes.
+///
+/// Adapters compose with [`Ldap::search_with_adapter()`](../struct.Ldap.html),
+/// which is what sync and async callers use to wrap any search without
+/// reimplementing the collection loop.
+pub trait Adapter: Send {
+    /// Called before the Search is sent. Returning
+    /// [`AdapterAction::Serve`](enum.AdapterAction.html) skips the wire round trip.
+    fn pre_search(&mut self, _key: &SearchKey) -> AdapterAction {
+        AdapterAction::Proceed
+    }
+
+    /// Called before each wire request a Search makes, including the first, to
+    /// decide whether (another) request should go out. `page` is `0` for the
+    /// first request and `prior` is the previous request's result, if any.
+    ///
+    /// Returning `Some(ctrls)` issues a request with `ctrls` attached (in
+    /// addition to any controls already set on the handle) and, once it
+    /// returns, calls this again with `page + 1`. Returning `None` ends the
+    /// Search; the entries collected across every request made so far, plus
+    /// the last request's `LdapResult`, are passed to `post_search`.
+    ///
+    /// The default makes exactly one request with no extra controls, which is
+    /// what every adapter besides [`PagedResults`](struct.PagedResults.html)
+    /// wants.
+    fn next_request(&mut self, page: u32, _prior: Option<&LdapResult>) -> Option<Vec<RawControl>> {
+        if page == 0 {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+
+    /// Called after the Search completes on the wire, with the chance to record or
+    /// transform the result before it's handed back to the caller.
+    fn post_search(&mut self, _key: &SearchKey, result: SearchResult) -> SearchResult {
+        result
+    }
+}
+
+/// An adapter that drops the final `LdapResult` status in favor of a default
+/// success, useful when a caller only cares about the returned entries.
+#[derive(Debug, Default)]
+pub struct EntriesOnly;
+
+impl Adapter for EntriesOnly {
+    fn post_search(&mut self, _key: &SearchKey, result: SearchResult) -> SearchResult {
+        SearchResult(result.0, LdapResult::default())
+    }
+}
+
+/// The OID of the Simple Paged Results control (RFC 2696).
+const PAGED_RESULTS_OID: &str = "1.2.840.113556.1.4.319";
+
+/// Build the request control value: `SEQUENCE { size INTEGER, cookie OCTET STRING }`.
+fn encode_paged_results_control(page_size: i32, cookie: &[u8]) -> Vec<u8> {
+    let mut seq = Vec::new();
+    ber::encode_tagged(&mut seq, 0x02, &encode_ber_integer(page_size as i64));
+    ber::encode_tagged(&mut seq, 0x04, cookie);
+    ber::wrap_sequence(&seq)
+}
+
+/// Parse a response control value, returning the cookie the server sent back.
+fn decode_paged_results_cookie(val: &[u8]) -> Vec<u8> {
+    ber::iter_tlv(ber::sequence_contents(val))
+        .find(|(tag, _)| *tag == 0x04)
+        .map(|(_, content)| content.to_vec())
+        .unwrap_or_default()
+}
+
+fn encode_ber_integer(n: i64) -> Vec<u8> {
+    let mut bytes = n.to_be_bytes().to_vec();
+    while bytes.len() > 1 && bytes[0] == 0 && bytes[1] & 0x80 == 0 {
+        bytes.remove(0);
+    }
+    bytes
+}
+
+/// An adapter that requests results a page at a time via the Simple Paged Results
+/// control (RFC 2696), transparently fetching and concatenating every page.
+#[derive(Debug)]
+pub struct PagedResults {
+    page_size: i32,
+    cookie: Vec<u8>,
+}
+
+impl PagedResults {
+    pub fn new(page_size: i32) -> Self {
+        PagedResults {
+            page_size,
+            cookie: Vec::new(),
+        }
+    }
+
+    fn request_control(&self) -> RawControl {
+        RawControl {
+            ctype: PAGED_RESULTS_OID.to_string(),
+            crit: false,
+            val: Some(encode_paged_results_control(self.page_size, &self.cookie)),
+        }
+    }
+}
+
+impl Adapter for PagedResults {
+    fn next_request(&mut self, page: u32, prior: Option<&LdapResult>) -> Option<Vec<RawControl>> {
+        if page > 0 {
+            let cookie = prior
+                .and_then(|r| r.ctrls.iter().find(|c| c.ctype == PAGED_RESULTS_OID))
+                .and_then(|c| c.val.as_deref())
+                .map(decode_paged_results_cookie)
+                .unwrap_or_default();
+            if cookie.is_empty() {
+                // The server signals the last page with an empty cookie; reset
+                // so this adapter can be reused for another Search.
+                self.cookie.clear();
+                return None;
+            }
+            self.cookie = cookie;
+        }
+        Some(vec![self.request_control()])
+    }
+}
+
+struct CacheEntry {
+    entries: Vec<ResultEntry>,
+    result: LdapResult,
+    expires_at: Instant,
+}
+
+/// A bounded, TTL-expiring LRU cache for Search results, keyed by the tuple of base
+/// DN, scope, normalized filter, requested attribute set, and serialized controls.
+///
+/// On a hit, `post_search` is never reached: [`pre_search`](#method.pre_search)
+/// replays the cached entries directly, without a wire round trip, and the entry's
+/// TTL is refreshed and its recency bumped. On a miss, the Search proceeds as
+/// normal and its result is recorded for next time. Entries are evicted least-
+/// recently-used first once `max_entries` is exceeded, or once their individual
+/// TTL elapses. A `max_entries` of `0` disables caching entirely.
+pub struct Cache {
+    max_entries: usize,
+    ttl: Duration,
+    /// Recency order, least recently used first.
+    order: Vec<SearchKey>,
+    store: HashMap<SearchKey, CacheEntry>,
+}
+
+impl Cache {
+    /// Create a cache holding at most `max_entries` results, each valid for `ttl`
+    /// after being recorded or last served from cache.
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Cache {
+            max_entries,
+            ttl,
+            order: Vec::new(),
+            store: HashMap::new(),
+        }
+    }
+
+    fn evict_expired(&mut self) {
+        let now = Instant::now();
+        self.store.retain(|_, e| e.expires_at > now);
+        let store = &self.store;
+        self.order.retain(|k| store.contains_key(k));
+    }
+
+    /// Move `key` to the most-recently-used end of `order`.
+    fn touch(&mut self, key: &SearchKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos);
+            self.order.push(key);
+        }
+    }
+
+    fn evict_lru(&mut self) {
+        while self.store.len() >= self.max_entries && !self.order.is_empty() {
+            let lru = self.order.remove(0);
+            self.store.remove(&lru);
+        }
+    }
+}
+
+impl Adapter for Cache {
+    fn pre_search(&mut self, key: &SearchKey) -> AdapterAction {
+        self.evict_expired();
+        if !self.store.contains_key(key) {
+            return AdapterAction::Proceed;
+        }
+        self.touch(key);
+        let expires_at = Instant::now() + self.ttl;
+        let entry = self.store.get_mut(key).expect("checked above");
+        entry.expires_at = expires_at;
+        AdapterAction::Serve(SearchResult(entry.entries.clone(), entry.result.clone()))
+    }
+
+    fn post_search(&mut self, key: &SearchKey, result: SearchResult) -> SearchResult {
+        if self.max_entries == 0 {
+            return result;
+        }
+        self.evict_expired();
+        self.evict_lru();
+        self.store.insert(
+            key.clone(),
+            CacheEntry {
+                entries: result.0.clone(),
+                result: result.1.clone(),
+                expires_at: Instant::now() + self.ttl,
+            },
+        );
+        self.order.push(key.clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::LdapResult;
+
+    fn key(filter: &str) -> SearchKey {
+        SearchKey::new("dc=example,dc=org", Scope::Subtree, filter, &["cn"], &[])
+    }
+
+    fn result(tag: &str) -> SearchResult {
+        SearchResult(
+            vec![ResultEntry(tag.to_string(), HashMap::new())],
+            LdapResult::default(),
+        )
+    }
+
+    #[test]
+    fn filter_normalization_collapses_whitespace_and_case_of_the_attribute_description() {
+        let a = key("(cn=Alice)");
+        let b = key("  (CN=Alice)  ");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn filter_normalization_leaves_the_asserted_value_case_intact() {
+        let a = key("(cn=Alice)");
+        let b = key("(cn=ALICE)");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn miss_then_hit_replays_without_proceeding() {
+        let mut cache = Cache::new(2, Duration::from_secs(60));
+        let k = key("(cn=alice)");
+        assert!(matches!(cache.pre_search(&k), AdapterAction::Proceed));
+        cache.post_search(&k, result("alice"));
+        match cache.pre_search(&k) {
+            AdapterAction::Serve(SearchResult(entries, _)) => {
+                assert_eq!(entries[0].0, "alice");
+            }
+            AdapterAction::Proceed => panic!("expected a cache hit"),
+        }
+    }
+
+    #[test]
+    fn zero_max_entries_disables_caching() {
+        let mut cache = Cache::new(0, Duration::from_secs(60));
+        let k = key("(cn=alice)");
+        cache.post_search(&k, result("alice"));
+        assert!(matches!(cache.pre_search(&k), AdapterAction::Proceed));
+    }
+
+    #[test]
+    fn eviction_is_least_recently_used_not_fifo() {
+        let mut cache = Cache::new(2, Duration::from_secs(60));
+        let a = key("(cn=a)");
+        let b = key("(cn=b)");
+        let c = key("(cn=c)");
+
+        cache.post_search(&a, result("a"));
+        cache.post_search(&b, result("b"));
+        // Touch `a` so `b` becomes the least recently used entry.
+        assert!(matches!(cache.pre_search(&a), AdapterAction::Serve(_)));
+        cache.post_search(&c, result("c"));
+
+        assert!(matches!(cache.pre_search(&a), AdapterAction::Serve(_)));
+        assert!(matches!(cache.pre_search(&c), AdapterAction::Serve(_)));
+        assert!(matches!(cache.pre_search(&b), AdapterAction::Proceed));
+    }
+
+    #[test]
+    fn ttl_expiry_evicts_entry() {
+        let mut cache = Cache::new(2, Duration::from_millis(0));
+        let k = key("(cn=alice)");
+        cache.post_search(&k, result("alice"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(matches!(cache.pre_search(&k), AdapterAction::Proceed));
+    }
+
+    fn paged_result_with_cookie(cookie: &[u8]) -> LdapResult {
+        LdapResult {
+            ctrls: vec![RawControl {
+                ctype: PAGED_RESULTS_OID.to_string(),
+                crit: false,
+                val: Some(encode_paged_results_control(0, cookie)),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn paged_results_control_round_trips_the_cookie() {
+        let encoded = encode_paged_results_control(100, b"page-2");
+        assert_eq!(decode_paged_results_cookie(&encoded), b"page-2");
+    }
+
+    #[test]
+    fn paged_results_requests_the_first_page_with_an_empty_cookie() {
+        let mut paging = PagedResults::new(50);
+        let ctrls = paging.next_request(0, None).expect("first page is always requested");
+        let ctrl = &ctrls[0];
+        assert_eq!(ctrl.ctype, PAGED_RESULTS_OID);
+        assert_eq!(decode_paged_results_cookie(ctrl.val.as_deref().unwrap()), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn paged_results_keeps_requesting_while_the_server_returns_a_cookie() {
+        let mut paging = PagedResults::new(50);
+        assert!(paging.next_request(0, None).is_some());
+        let page1 = paged_result_with_cookie(b"more");
+        let ctrls = paging
+            .next_request(1, Some(&page1))
+            .expect("server returned a cookie, so another page is fetched");
+        assert_eq!(
+            decode_paged_results_cookie(ctrls[0].val.as_deref().unwrap()),
+            b"more"
+        );
+    }
+
+    #[test]
+    fn paged_results_stops_once_the_server_returns_an_empty_cookie() {
+        let mut paging = PagedResults::new(50);
+        assert!(paging.next_request(0, None).is_some());
+        let last_page = paged_result_with_cookie(b"");
+        assert!(paging.next_request(1, Some(&last_page)).is_none());
+    }
+}
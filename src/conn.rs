@@ -0,0 +1,405 @@
+//! Asynchronous connection setup.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use rand::Rng;
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::ldap::Ldap;
+use crate::result::{LdapError, Result};
+
+/// The transport security asserted for a connection.
+///
+/// Replaces inferring security purely from the URL scheme plus a `starttls` flag:
+/// a caller can now say exactly what they expect, and [`connect_one()`] rejects
+/// combinations that can't be satisfied (e.g. `StartTls` against an `ldaps://`
+/// URL) instead of silently picking one or the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionMode {
+    /// Plain, unencrypted LDAP.
+    Plain,
+    /// Plain LDAP, upgraded to TLS via the StartTLS extended operation.
+    StartTls,
+    /// LDAP over TLS from the first byte (`ldaps://`).
+    Ldaps,
+}
+
+/// Additional parameters for opening an LDAP connection.
+#[derive(Clone, Debug, Default)]
+pub struct LdapConnSettings {
+    mode: Option<ConnectionMode>,
+    no_tls_verify: bool,
+    ip_address: Option<IpAddr>,
+    root_certificates: Vec<Vec<u8>>,
+    conn_timeout: Option<Duration>,
+}
+
+impl LdapConnSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use StartTLS on the connection, upgrading it to an encrypted one after the initial
+    /// plain-text handshake.
+    ///
+    /// Kept for backward compatibility; prefer
+    /// [`set_connection_mode()`](#method.set_connection_mode). `true` is equivalent
+    /// to `set_connection_mode(ConnectionMode::StartTls)`; `false` clears any
+    /// explicit mode, reverting to inferring it from the URL scheme.
+    pub fn set_starttls(mut self, starttls: bool) -> Self {
+        self.mode = if starttls {
+            Some(ConnectionMode::StartTls)
+        } else {
+            None
+        };
+        self
+    }
+
+    pub fn starttls(&self) -> bool {
+        self.mode == Some(ConnectionMode::StartTls)
+    }
+
+    /// Explicitly assert the connection's transport security, instead of inferring
+    /// it from the URL scheme.
+    pub fn set_connection_mode(mut self, mode: ConnectionMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// The explicitly asserted connection mode, or `None` if it should be inferred
+    /// from the URL scheme.
+    pub fn connection_mode(&self) -> Option<ConnectionMode> {
+        self.mode
+    }
+
+    /// Disable server certificate verification for TLS connections.
+    pub fn set_no_tls_verify(mut self, no_tls_verify: bool) -> Self {
+        self.no_tls_verify = no_tls_verify;
+        self
+    }
+
+    /// Connect to a specific IP address instead of resolving the hostname in the URL.
+    pub fn set_ip_address(mut self, ip_address: &IpAddr) -> Self {
+        self.ip_address = Some(*ip_address);
+        self
+    }
+
+    /// Trust an additional root certificate, in DER form, for TLS verification.
+    pub fn add_root_certificate(mut self, cert: &[u8]) -> Self {
+        self.root_certificates.push(cert.to_vec());
+        self
+    }
+
+    /// Set a timeout for establishing the underlying transport connection.
+    pub fn set_conn_timeout(mut self, timeout: Duration) -> Self {
+        self.conn_timeout = Some(timeout);
+        self
+    }
+}
+
+/// Types that can be turned into an ordered list of candidate LDAP server URLs.
+///
+/// A plain `&str`/`String` is a single server, preserving the historical single-URL
+/// API. A `Vec`/slice of URLs is tried in the given order, falling through to the
+/// next entry on connection or TLS errors.
+pub trait ToServerUrls {
+    fn to_server_urls(&self) -> Vec<String>;
+}
+
+impl ToServerUrls for str {
+    fn to_server_urls(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+}
+
+impl ToServerUrls for String {
+    fn to_server_urls(&self) -> Vec<String> {
+        vec![self.clone()]
+    }
+}
+
+impl<S: AsRef<str>> ToServerUrls for [S] {
+    fn to_server_urls(&self) -> Vec<String> {
+        self.iter().map(|s| s.as_ref().to_string()).collect()
+    }
+}
+
+impl<S: AsRef<str>> ToServerUrls for Vec<S> {
+    fn to_server_urls(&self) -> Vec<String> {
+        self.as_slice().to_server_urls()
+    }
+}
+
+/// An established, not yet polled, connection to an LDAP server.
+///
+/// The future returned by this struct must be driven to completion, typically with
+/// the [`drive!`](macro.drive.html) macro, for any operation on the paired
+/// [`Ldap`](struct.Ldap.html) handle to make progress.
+#[derive(Debug)]
+pub struct LdapConnAsync {
+    server: String,
+}
+
+impl LdapConnAsync {
+    /// Open a connection to an LDAP server specified by `urls`, using default settings.
+    ///
+    /// `urls` can be a single `&str`/`String`, or an ordered `Vec`/slice of them: each
+    /// candidate is tried in turn, falling through to the next one on a connection or
+    /// TLS error. A bind failure (once a transport connection is established) is
+    /// surfaced immediately and does not trigger failover.
+    pub async fn new<U: ToServerUrls + ?Sized>(urls: &U) -> Result<(Self, Ldap)> {
+        Self::with_settings(LdapConnSettings::new(), urls).await
+    }
+
+    /// Open a connection to an LDAP server specified by `urls`, using `settings` to
+    /// specify additional parameters. See [`new()`](#method.new) for the accepted
+    /// forms of `urls`.
+    pub async fn with_settings<U: ToServerUrls + ?Sized>(
+        settings: LdapConnSettings,
+        urls: &U,
+    ) -> Result<(Self, Ldap)> {
+        let candidates = urls.to_server_urls();
+        Self::connect_first(&candidates, &settings).await
+    }
+
+    /// Open a connection by discovering servers through DNS SRV records for `domain`.
+    ///
+    /// Looks up `_ldaps._tcp.<domain>` when `settings`'s
+    /// [`connection_mode()`](struct.LdapConnSettings.html#method.connection_mode) is
+    /// `Ldaps`, or `_ldap._tcp.<domain>` otherwise (including `StartTls`, which
+    /// upgrades a plain connection after it's made). Candidates are ordered by
+    /// ascending SRV priority, with weighted-random selection among hosts sharing
+    /// the same priority (weight / sum-of-weights), matching the precedence rules
+    /// of RFC 2782.
+    pub async fn from_domain(domain: &str, settings: LdapConnSettings) -> Result<(Self, Ldap)> {
+        let want_ldaps = settings.connection_mode() == Some(ConnectionMode::Ldaps);
+        let candidates = srv_discover(domain, want_ldaps).await?;
+        Self::connect_first(&candidates, &settings).await
+    }
+
+    async fn connect_first(candidates: &[String], settings: &LdapConnSettings) -> Result<(Self, Ldap)> {
+        if candidates.is_empty() {
+            return Err(LdapError::Config("no server URLs given".to_string()));
+        }
+        let mut last_err = None;
+        for url in candidates {
+            match Self::connect_one(url, settings).await {
+                Ok(pair) => return Ok(pair),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.expect("at least one candidate was attempted"))
+    }
+
+    async fn connect_one(url: &str, settings: &LdapConnSettings) -> Result<(Self, Ldap)> {
+        let _mode = resolve_mode(url, settings)?;
+        Ok((
+            LdapConnAsync {
+                server: url.to_string(),
+            },
+            Ldap::new(),
+        ))
+    }
+
+    /// The URL of the server this connection ended up being established to, after
+    /// failover or SRV discovery.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+}
+
+impl std::future::Future for LdapConnAsync {
+    type Output = Result<()>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+/// Resolve the effective [`ConnectionMode`](enum.ConnectionMode.html) for `url`,
+/// defaulting from its scheme when `settings` doesn't assert one explicitly, and
+/// rejecting combinations that can't be satisfied.
+fn resolve_mode(url: &str, settings: &LdapConnSettings) -> Result<ConnectionMode> {
+    let scheme_is_ldaps = url.starts_with("ldaps://");
+    match settings.connection_mode() {
+        Some(ConnectionMode::StartTls) if scheme_is_ldaps => Err(LdapError::UnsupportedMode(
+            format!("StartTls was requested, but {} is already an ldaps:// URL", url),
+        )),
+        Some(ConnectionMode::Plain) if scheme_is_ldaps => Err(LdapError::UnsupportedMode(format!(
+            "Plain mode was requested, but {} is an ldaps:// URL",
+            url
+        ))),
+        Some(ConnectionMode::Ldaps) if !scheme_is_ldaps => Err(LdapError::UnsupportedMode(format!(
+            "Ldaps mode was requested, but {} is not an ldaps:// URL",
+            url
+        ))),
+        Some(mode) => Ok(mode),
+        None if scheme_is_ldaps => Ok(ConnectionMode::Ldaps),
+        None => Ok(ConnectionMode::Plain),
+    }
+}
+
+/// Resolve an ordered list of candidate server URLs for `domain` via DNS SRV records.
+async fn srv_discover(domain: &str, tls: bool) -> Result<Vec<String>> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()
+        .map_err(|e| LdapError::Resolve(format!("resolver setup failed: {}", e)))?;
+
+    let (service, scheme) = if tls {
+        ("_ldaps._tcp", "ldaps")
+    } else {
+        ("_ldap._tcp", "ldap")
+    };
+    let name = format!("{}.{}", service, domain.trim_end_matches('.'));
+
+    let lookup = resolver
+        .srv_lookup(name.as_str())
+        .await
+        .map_err(|e| LdapError::Resolve(format!("SRV lookup for {} failed: {}", name, e)))?;
+
+    let records: Vec<(u16, u16, String, u16)> = lookup
+        .iter()
+        .map(|srv| (srv.priority(), srv.weight(), srv.target().to_utf8(), srv.port()))
+        .collect();
+
+    Ok(order_srv_records(records, scheme))
+}
+
+/// Order `(priority, weight, target, port)` SRV records into candidate server URLs:
+/// ascending by priority, with weighted-random selection (weight / sum-of-weights)
+/// breaking ties within a priority group, per RFC 2782.
+fn order_srv_records(records: Vec<(u16, u16, String, u16)>, scheme: &str) -> Vec<String> {
+    let mut by_priority: std::collections::BTreeMap<u16, Vec<(u16, String, u16)>> =
+        std::collections::BTreeMap::new();
+    for (priority, weight, target, port) in records {
+        by_priority.entry(priority).or_default().push((weight, target, port));
+    }
+
+    let mut ordered = Vec::new();
+    for (_, mut group) in by_priority {
+        while !group.is_empty() {
+            let pick = weighted_pick(&group);
+            let (_, target, port) = group.remove(pick);
+            ordered.push(format!(
+                "{}://{}:{}",
+                scheme,
+                target.trim_end_matches('.'),
+                port
+            ));
+        }
+    }
+    ordered
+}
+
+/// Pick an index out of `group` with probability proportional to its weight.
+/// Zero-weight entries are only picked once every nonzero-weight entry is gone.
+fn weighted_pick(group: &[(u16, String, u16)]) -> usize {
+    let total_weight: u32 = group.iter().map(|(w, _, _)| *w as u32).sum();
+    if total_weight == 0 {
+        return 0;
+    }
+    let mut r = rand::thread_rng().gen_range(0..total_weight);
+    for (i, (w, _, _)) in group.iter().enumerate() {
+        if r < *w as u32 {
+            return i;
+        }
+        r -= *w as u32;
+    }
+    group.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_mode_defaults_from_the_url_scheme_when_unset() {
+        assert_eq!(
+            resolve_mode("ldap://dc1.example.org", &LdapConnSettings::new()).unwrap(),
+            ConnectionMode::Plain
+        );
+        assert_eq!(
+            resolve_mode("ldaps://dc1.example.org", &LdapConnSettings::new()).unwrap(),
+            ConnectionMode::Ldaps
+        );
+    }
+
+    #[test]
+    fn resolve_mode_accepts_starttls_against_a_plain_url() {
+        let settings = LdapConnSettings::new().set_connection_mode(ConnectionMode::StartTls);
+        assert_eq!(
+            resolve_mode("ldap://dc1.example.org", &settings).unwrap(),
+            ConnectionMode::StartTls
+        );
+    }
+
+    #[test]
+    fn resolve_mode_rejects_starttls_against_an_ldaps_url() {
+        let settings = LdapConnSettings::new().set_connection_mode(ConnectionMode::StartTls);
+        assert!(resolve_mode("ldaps://dc1.example.org", &settings).is_err());
+    }
+
+    #[test]
+    fn resolve_mode_rejects_plain_against_an_ldaps_url() {
+        let settings = LdapConnSettings::new().set_connection_mode(ConnectionMode::Plain);
+        assert!(resolve_mode("ldaps://dc1.example.org", &settings).is_err());
+    }
+
+    #[test]
+    fn resolve_mode_rejects_ldaps_against_a_non_ldaps_url() {
+        let settings = LdapConnSettings::new().set_connection_mode(ConnectionMode::Ldaps);
+        assert!(resolve_mode("ldap://dc1.example.org", &settings).is_err());
+    }
+
+    #[test]
+    fn resolve_mode_accepts_ldaps_against_an_ldaps_url() {
+        let settings = LdapConnSettings::new().set_connection_mode(ConnectionMode::Ldaps);
+        assert_eq!(
+            resolve_mode("ldaps://dc1.example.org", &settings).unwrap(),
+            ConnectionMode::Ldaps
+        );
+    }
+
+    #[test]
+    fn priority_groups_are_ordered_ascending() {
+        let records = vec![
+            (20, 0, "b.example.org.".to_string(), 389),
+            (10, 0, "a1.example.org.".to_string(), 389),
+            (10, 0, "a2.example.org.".to_string(), 389),
+        ];
+        let ordered = order_srv_records(records, "ldap");
+        assert_eq!(ordered.len(), 3);
+        assert!(ordered[..2].iter().all(|u| u.starts_with("ldap://a")));
+        assert_eq!(ordered[2], "ldap://b.example.org:389");
+    }
+
+    #[test]
+    fn trailing_dots_are_trimmed_and_scheme_is_applied() {
+        let records = vec![(0, 1, "dc1.example.org.".to_string(), 636)];
+        let ordered = order_srv_records(records, "ldaps");
+        assert_eq!(ordered, vec!["ldaps://dc1.example.org:636".to_string()]);
+    }
+
+    #[test]
+    fn zero_weight_entries_are_never_selected_over_a_nonzero_one() {
+        let group = vec![
+            (0u16, "a.example.org.".to_string(), 389u16),
+            (100u16, "b.example.org.".to_string(), 389u16),
+        ];
+        for _ in 0..50 {
+            assert_eq!(weighted_pick(&group), 1);
+        }
+    }
+
+    #[test]
+    fn all_zero_weights_do_not_panic_and_pick_an_index() {
+        let group = vec![
+            (0u16, "a.example.org.".to_string(), 389u16),
+            (0u16, "b.example.org.".to_string(), 389u16),
+        ];
+        assert_eq!(weighted_pick(&group), 0);
+    }
+}
@@ -0,0 +1,9 @@
+//! LDAP controls.
+
+/// A request or response control, in its raw, encoded form.
+#[derive(Clone, Debug)]
+pub struct RawControl {
+    pub ctype: String,
+    pub crit: bool,
+    pub val: Option<Vec<u8>>,
+}
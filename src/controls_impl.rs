@@ -0,0 +1,20 @@
+//! Conversion helpers for passing controls into operations.
+
+use crate::controls::RawControl;
+
+/// Types convertible into a vector of raw controls.
+pub trait IntoRawControlVec {
+    fn into(self) -> Vec<RawControl>;
+}
+
+impl IntoRawControlVec for Vec<RawControl> {
+    fn into(self) -> Vec<RawControl> {
+        self
+    }
+}
+
+impl IntoRawControlVec for RawControl {
+    fn into(self) -> Vec<RawControl> {
+        vec![self]
+    }
+}
@@ -0,0 +1,242 @@
+//! Extended operations.
+
+/// A generic LDAP Extended operation (RFC 4511 §4.12).
+#[derive(Clone, Debug, Default)]
+pub struct Exop {
+    pub name: Option<String>,
+    pub val: Option<Vec<u8>>,
+}
+
+/// The RFC 3062 Password Modify Extended operation.
+///
+/// All three fields are optional, per the RFC: an absent `userIdentity` means "the
+/// user currently bound as", and an absent `newPasswd` asks the server to generate
+/// one, returned in the response as [`gen_passwd`](PasswordModifyResp::gen_passwd).
+#[derive(Clone, Debug, Default)]
+pub struct PasswordModify {
+    pub user_identity: Option<Vec<u8>>,
+    pub old_passwd: Option<Vec<u8>>,
+    pub new_passwd: Option<Vec<u8>>,
+}
+
+impl PasswordModify {
+    /// The OID of the Password Modify Extended operation, as assigned by RFC 3062.
+    pub const OID: &'static str = "1.3.6.1.4.1.4203.1.11.1";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `userIdentity` field, identifying the user whose password is changed.
+    pub fn user_identity<B: Into<Vec<u8>>>(mut self, id: B) -> Self {
+        self.user_identity = Some(id.into());
+        self
+    }
+
+    /// Set the `oldPasswd` field, the user's current password.
+    pub fn old_passwd<B: Into<Vec<u8>>>(mut self, pw: B) -> Self {
+        self.old_passwd = Some(pw.into());
+        self
+    }
+
+    /// Set the `newPasswd` field. If omitted, the server is asked to generate one.
+    pub fn new_passwd<B: Into<Vec<u8>>>(mut self, pw: B) -> Self {
+        self.new_passwd = Some(pw.into());
+        self
+    }
+}
+
+impl From<PasswordModify> for Exop {
+    fn from(pm: PasswordModify) -> Exop {
+        let mut seq = Vec::new();
+        if let Some(ref v) = pm.user_identity {
+            ber::encode_tagged(&mut seq, 0x80, v);
+        }
+        if let Some(ref v) = pm.old_passwd {
+            ber::encode_tagged(&mut seq, 0x81, v);
+        }
+        if let Some(ref v) = pm.new_passwd {
+            ber::encode_tagged(&mut seq, 0x82, v);
+        }
+        Exop {
+            name: Some(PasswordModify::OID.to_string()),
+            val: Some(ber::wrap_sequence(&seq)),
+        }
+    }
+}
+
+/// The parsed response of a [`PasswordModify`](struct.PasswordModify.html) operation.
+#[derive(Clone, Debug, Default)]
+pub struct PasswordModifyResp {
+    /// The password generated by the server, present when `newPasswd` was omitted
+    /// from the request.
+    pub gen_passwd: Option<Vec<u8>>,
+}
+
+impl PasswordModifyResp {
+    /// Parse the optional response SEQUENCE of a Password Modify operation.
+    pub fn parse(val: &[u8]) -> Self {
+        let mut resp = PasswordModifyResp::default();
+        for (tag, content) in ber::iter_tlv(ber::sequence_contents(val)) {
+            if tag == 0x80 {
+                resp.gen_passwd = Some(content.to_vec());
+            }
+        }
+        resp
+    }
+}
+
+/// A minimal BER encoder/decoder for the handful of constructs needed by typed
+/// Extended operations: a top-level SEQUENCE containing zero or more primitive,
+/// context-tagged OCTET STRING fields.
+pub(crate) mod ber {
+    pub fn encode_len(out: &mut Vec<u8>, len: usize) {
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            let bytes = len.to_be_bytes();
+            let significant: Vec<u8> = bytes
+                .iter()
+                .copied()
+                .skip_while(|&b| b == 0)
+                .collect();
+            out.push(0x80 | significant.len() as u8);
+            out.extend_from_slice(&significant);
+        }
+    }
+
+    pub fn encode_tagged(out: &mut Vec<u8>, tag: u8, val: &[u8]) {
+        out.push(tag);
+        encode_len(out, val.len());
+        out.extend_from_slice(val);
+    }
+
+    pub fn wrap_sequence(contents: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(contents.len() + 4);
+        out.push(0x30);
+        encode_len(&mut out, contents.len());
+        out.extend_from_slice(contents);
+        out
+    }
+
+    fn decode_len(buf: &[u8]) -> Option<(usize, &[u8])> {
+        let (&first, rest) = buf.split_first()?;
+        if first & 0x80 == 0 {
+            Some((first as usize, rest))
+        } else {
+            let n = (first & 0x7f) as usize;
+            if rest.len() < n {
+                return None;
+            }
+            let (len_bytes, rest) = rest.split_at(n);
+            let mut len = 0usize;
+            for b in len_bytes {
+                len = (len << 8) | *b as usize;
+            }
+            Some((len, rest))
+        }
+    }
+
+    /// Unwrap a top-level SEQUENCE, returning its contents (or an empty slice if
+    /// `val` isn't a well-formed SEQUENCE).
+    pub fn sequence_contents(val: &[u8]) -> &[u8] {
+        match val.split_first() {
+            Some((0x30, rest)) => match decode_len(rest) {
+                Some((len, body)) if body.len() >= len => &body[..len],
+                _ => &[],
+            },
+            _ => &[],
+        }
+    }
+
+    /// Iterate over the top-level TLVs of `buf`, yielding `(tag, contents)`.
+    pub fn iter_tlv(mut buf: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+        std::iter::from_fn(move || {
+            let (&tag, rest) = buf.split_first()?;
+            let (len, rest) = decode_len(rest)?;
+            if rest.len() < len {
+                return None;
+            }
+            let (content, rest) = rest.split_at(len);
+            buf = rest;
+            Some((tag, content))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_oid_and_wraps_a_sequence() {
+        let exop: Exop = PasswordModify::new()
+            .user_identity(b"dn:uid=jdoe,dc=example,dc=org".to_vec())
+            .old_passwd(b"hunter2".to_vec())
+            .new_passwd(b"correct horse battery staple".to_vec())
+            .into();
+        assert_eq!(exop.name.as_deref(), Some(PasswordModify::OID));
+        let val = exop.val.expect("request carries a value");
+        assert_eq!(val[0], 0x30, "top-level tag must be a SEQUENCE");
+    }
+
+    #[test]
+    fn request_fields_round_trip_through_ber() {
+        let exop: Exop = PasswordModify::new()
+            .user_identity(b"jdoe".to_vec())
+            .old_passwd(b"old".to_vec())
+            .new_passwd(b"new".to_vec())
+            .into();
+        let val = exop.val.unwrap();
+        let fields: Vec<(u8, Vec<u8>)> = ber::iter_tlv(ber::sequence_contents(&val))
+            .map(|(tag, content)| (tag, content.to_vec()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (0x80, b"jdoe".to_vec()),
+                (0x81, b"old".to_vec()),
+                (0x82, b"new".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn omitted_fields_are_not_encoded() {
+        let exop: Exop = PasswordModify::new().old_passwd(b"old".to_vec()).into();
+        let val = exop.val.unwrap();
+        let fields: Vec<u8> = ber::iter_tlv(ber::sequence_contents(&val))
+            .map(|(tag, _)| tag)
+            .collect();
+        assert_eq!(fields, vec![0x81]);
+    }
+
+    #[test]
+    fn long_values_round_trip_through_the_multi_byte_length_form() {
+        let long_value = vec![b'x'; 200];
+        let mut encoded = Vec::new();
+        ber::encode_tagged(&mut encoded, 0x80, &long_value);
+        let wrapped = ber::wrap_sequence(&encoded);
+        let (tag, content) = ber::iter_tlv(ber::sequence_contents(&wrapped))
+            .next()
+            .expect("one field was encoded");
+        assert_eq!(tag, 0x80);
+        assert_eq!(content, long_value.as_slice());
+    }
+
+    #[test]
+    fn response_parses_gen_passwd() {
+        let mut seq = Vec::new();
+        ber::encode_tagged(&mut seq, 0x80, b"generated-pw");
+        let val = ber::wrap_sequence(&seq);
+        let resp = PasswordModifyResp::parse(&val);
+        assert_eq!(resp.gen_passwd.as_deref(), Some(&b"generated-pw"[..]));
+    }
+
+    #[test]
+    fn response_without_gen_passwd_parses_to_none() {
+        let val = ber::wrap_sequence(&[]);
+        let resp = PasswordModifyResp::parse(&val);
+        assert_eq!(resp.gen_passwd, None);
+    }
+}
@@ -0,0 +1,267 @@
+//! The core asynchronous LDAP handle.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::adapters::{Adapter, AdapterAction, SearchKey};
+use crate::controls::RawControl;
+use crate::exop::{Exop, PasswordModify, PasswordModifyResp};
+use crate::result::{CompareResult, ExopResult, LdapResult, Result, SearchResult};
+use crate::search::{RootDSE, Scope, SearchEntry, SearchOptions, SearchStream};
+use crate::RequestId;
+
+/// A modification to be applied to an attribute, as part of a Modify operation.
+#[derive(Clone, Debug)]
+pub enum Mod<S> {
+    Add(S, HashSet<S>),
+    Delete(S, HashSet<S>),
+    Replace(S, HashSet<S>),
+}
+
+/// A handle for performing LDAP operations on an open connection.
+///
+/// `Ldap` is cheaply cloneable; all clones share the same underlying connection.
+#[derive(Clone, Debug)]
+pub struct Ldap {
+    pub(crate) search_opts: Option<SearchOptions>,
+    pub(crate) controls: Option<Vec<RawControl>>,
+    pub(crate) timeout: Option<Duration>,
+    last_id: RequestId,
+}
+
+impl Ldap {
+    pub(crate) fn new() -> Self {
+        Ldap {
+            search_opts: None,
+            controls: None,
+            timeout: None,
+            last_id: 0,
+        }
+    }
+
+    /// Set the search options to be used for subsequent searches.
+    pub fn with_search_options(&mut self, opts: SearchOptions) -> &mut Self {
+        self.search_opts = Some(opts);
+        self
+    }
+
+    /// Set the controls to be sent with the next operation.
+    pub fn with_controls<V: Into<Vec<RawControl>>>(&mut self, ctrls: V) -> &mut Self {
+        self.controls = Some(ctrls.into());
+        self
+    }
+
+    /// Set the timeout for subsequent operations.
+    pub fn with_timeout(&mut self, duration: Duration) -> &mut Self {
+        self.timeout = Some(duration);
+        self
+    }
+
+    pub async fn simple_bind(&mut self, _bind_dn: &str, _bind_pw: &str) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn sasl_external_bind(&mut self) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn sasl_spnego_bind(&mut self, _username: &str, _password: &str) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn search<S: AsRef<str>>(
+        &mut self,
+        _base: &str,
+        _scope: Scope,
+        _filter: &str,
+        _attrs: Vec<S>,
+    ) -> Result<SearchResult> {
+        Ok(SearchResult(Vec::new(), LdapResult::default()))
+    }
+
+    /// Perform a Search through `adapter`, which gets a chance to short-circuit the
+    /// wire round trip (e.g. serve a cached result) and to observe or rewrite the
+    /// result once the Search completes. See the [`adapters`](adapters/index.html)
+    /// module for the built-in [`Cache`](adapters/struct.Cache.html),
+    /// [`PagedResults`](adapters/struct.PagedResults.html), and
+    /// [`EntriesOnly`](adapters/struct.EntriesOnly.html) adapters.
+    pub async fn search_with_adapter<S: AsRef<str> + Clone>(
+        &mut self,
+        adapter: &mut dyn Adapter,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: Vec<S>,
+    ) -> Result<SearchResult> {
+        let base_ctrls = self.controls.clone().unwrap_or_default();
+        let key = SearchKey::new(base, scope, filter, &attrs, &base_ctrls);
+        match adapter.pre_search(&key) {
+            AdapterAction::Serve(result) => Ok(result),
+            AdapterAction::Proceed => {
+                let mut entries = Vec::new();
+                let mut last_result = None;
+                let mut page = 0u32;
+                let final_result = loop {
+                    let extra_ctrls = match adapter.next_request(page, last_result.as_ref()) {
+                        Some(ctrls) => ctrls,
+                        None => break last_result.unwrap_or_default(),
+                    };
+                    if !extra_ctrls.is_empty() {
+                        let mut ctrls = base_ctrls.clone();
+                        ctrls.extend(extra_ctrls);
+                        self.with_controls(ctrls);
+                    }
+                    let result = self.search(base, scope, filter, attrs.clone()).await?;
+                    entries.extend(result.0);
+                    last_result = Some(result.1);
+                    page += 1;
+                };
+                let combined = SearchResult(entries, final_result);
+                Ok(adapter.post_search(&key, combined))
+            }
+        }
+    }
+
+    /// Retrieve and parse the server's root DSE.
+    ///
+    /// Performs a base-scoped Search against the empty DN with filter
+    /// `(objectClass=*)`, requesting the standard operational attributes that
+    /// describe server capabilities (supported controls, extensions, SASL
+    /// mechanisms, LDAP versions, naming contexts, and the subschema subentry).
+    pub async fn root_dse(&mut self) -> Result<RootDSE> {
+        let SearchResult(mut entries, result) = self
+            .search(
+                "",
+                Scope::Base,
+                "(objectClass=*)",
+                vec![
+                    "namingContexts",
+                    "supportedControl",
+                    "supportedExtension",
+                    "supportedSASLMechanisms",
+                    "supportedLDAPVersion",
+                    "defaultNamingContext",
+                    "subschemaSubentry",
+                ],
+            )
+            .await?;
+        result.success()?;
+        let entry = entries.pop().map(SearchEntry::construct).unwrap_or_else(|| {
+            SearchEntry::construct(crate::search::ResultEntry(
+                String::new(),
+                std::collections::HashMap::new(),
+            ))
+        });
+        Ok(RootDSE::construct(entry))
+    }
+
+    pub async fn streaming_search<S: AsRef<str>>(
+        &mut self,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: Vec<S>,
+    ) -> Result<SearchStream> {
+        let mut stream = self.clone().into_search_stream();
+        stream.start(base, scope, filter, attrs).await?;
+        Ok(stream)
+    }
+
+    /// Turn this handle into a [`SearchStream`](struct.SearchStream.html), to be
+    /// started explicitly with [`start()`](struct.SearchStream.html#method.start).
+    pub fn into_search_stream(self) -> SearchStream {
+        SearchStream {
+            ldap: self,
+            result: None,
+        }
+    }
+
+    pub async fn add<S: AsRef<[u8]> + Eq + Hash>(
+        &mut self,
+        _dn: &str,
+        _attrs: Vec<(S, HashSet<S>)>,
+    ) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn compare<B: AsRef<[u8]>>(
+        &mut self,
+        _dn: &str,
+        _attr: &str,
+        _val: B,
+    ) -> Result<CompareResult> {
+        Ok(CompareResult(LdapResult::default()))
+    }
+
+    pub async fn delete(&mut self, _dn: &str) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn modify<S: AsRef<[u8]> + Eq + Hash>(
+        &mut self,
+        _dn: &str,
+        _mods: Vec<Mod<S>>,
+    ) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn modifydn(
+        &mut self,
+        _dn: &str,
+        _rdn: &str,
+        _delete_old: bool,
+        _new_sup: Option<&str>,
+    ) -> Result<LdapResult> {
+        Ok(LdapResult::default())
+    }
+
+    pub async fn unbind(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn extended<E>(&mut self, _exop: E) -> Result<ExopResult>
+    where
+        E: Into<Exop>,
+    {
+        Ok(ExopResult(None, LdapResult::default()))
+    }
+
+    /// Change or reset a user's password via the RFC 3062 Password Modify Extended
+    /// operation, without hand-building the request `Exop`.
+    ///
+    /// `user_identity` defaults to the currently bound user when `None`. If
+    /// `new_passwd` is `None`, the server is asked to generate one, returned as the
+    /// second element of the tuple.
+    pub async fn modify_password(
+        &mut self,
+        user_identity: Option<Vec<u8>>,
+        old_passwd: Option<Vec<u8>>,
+        new_passwd: Option<Vec<u8>>,
+    ) -> Result<(LdapResult, Option<Vec<u8>>)> {
+        let mut pm = PasswordModify::new();
+        if let Some(id) = user_identity {
+            pm = pm.user_identity(id);
+        }
+        if let Some(old) = old_passwd {
+            pm = pm.old_passwd(old);
+        }
+        if let Some(new) = new_passwd {
+            pm = pm.new_passwd(new);
+        }
+        let ExopResult(exop, result) = self.extended(pm).await?;
+        result.clone().success()?;
+        let gen_passwd = exop
+            .and_then(|e| e.val)
+            .and_then(|v| PasswordModifyResp::parse(&v).gen_passwd);
+        Ok((result, gen_passwd))
+    }
+
+    pub fn last_id(&mut self) -> RequestId {
+        self.last_id
+    }
+
+    pub async fn abandon(&mut self, _msgid: RequestId) -> Result<()> {
+        Ok(())
+    }
+}
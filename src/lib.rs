@@ -0,0 +1,43 @@
+//! An LDAP library written in Rust.
+//!
+//! This library provides both synchronous ([`LdapConn`](struct.LdapConn.html)) and
+//! asynchronous ([`LdapConnAsync`](struct.LdapConnAsync.html)) clients for the Lightweight
+//! Directory Access Protocol.
+
+mod conn;
+mod controls_impl;
+mod exop;
+mod ldap;
+mod pool;
+mod search;
+mod sync;
+
+pub mod adapters;
+pub mod controls;
+pub mod result;
+
+pub use conn::{ConnectionMode, LdapConnAsync, LdapConnSettings, ToServerUrls};
+pub use exop::{Exop, PasswordModify, PasswordModifyResp};
+pub use ldap::{Ldap, Mod};
+pub use pool::{LdapPool, LdapPoolSettings, PooledLdap};
+pub use search::{ResultEntry, RootDSE, Scope, SearchEntry, SearchOptions, SearchStream};
+pub use sync::{EntryStream, LdapConn};
+
+/// Request ID, used for matching requests and responses made through the same
+/// connection, as well as disambiguating results in the streaming Search interface.
+pub type RequestId = i32;
+
+/// Drive a connection future on the current Tokio runtime, in the background.
+///
+/// This macro must be invoked after opening a connection, in order for any
+/// operations to be sent to the server.
+#[macro_export]
+macro_rules! drive {
+    ($conn:expr) => {
+        tokio::spawn(async move {
+            if let Err(e) = $conn.await {
+                log::warn!("LDAP connection error: {}", e);
+            }
+        });
+    };
+}
@@ -0,0 +1,289 @@
+//! A native connection pool for [`LdapConnAsync`](struct.LdapConnAsync.html).
+//!
+//! `LdapConn` is explicitly non-cloneable, and sharing a single `Ldap` handle
+//! across concurrent, independent operations requires care (each handle tracks its
+//! own `last_id`/search state). `LdapPool` manages a set of fully bound, live
+//! connections instead, so callers don't have to wrap `LdapConnAsync` with an
+//! external pooling crate to get credential storage, health checks and failover.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::conn::{LdapConnSettings, ToServerUrls};
+use crate::ldap::Ldap;
+use crate::result::{LdapError, Result};
+
+/// Sizing and health-check parameters for an [`LdapPool`](struct.LdapPool.html).
+#[derive(Clone, Debug)]
+pub struct LdapPoolSettings {
+    /// Number of connections opened and bound up front.
+    pub min_size: usize,
+    /// Maximum number of connections the pool will hold at once; also the limit
+    /// on concurrent checkouts, since [`get()`](struct.LdapPool.html#method.get)
+    /// opens a new connection on demand up to this bound.
+    pub max_size: usize,
+    /// Validate liveness with a lightweight root DSE read on checkout, discarding
+    /// and replacing connections that fail it.
+    pub health_check: bool,
+}
+
+impl Default for LdapPoolSettings {
+    fn default() -> Self {
+        LdapPoolSettings {
+            min_size: 0,
+            max_size: 8,
+            health_check: false,
+        }
+    }
+}
+
+/// A pool of `N` [`LdapConnAsync`](struct.LdapConnAsync.html) connections, each
+/// bound with the same stored credentials and opened against the same server
+/// list/settings.
+///
+/// Checking out a connection with [`get()`](#method.get) yields a
+/// [`PooledLdap`](struct.PooledLdap.html) guard that derefs to
+/// [`Ldap`](struct.Ldap.html) and returns the connection to the pool on drop.
+/// Connections that return a transport error while checked out should be
+/// discarded rather than returned; see [`PooledLdap::discard()`].
+pub struct LdapPool {
+    urls: Vec<String>,
+    conn_settings: LdapConnSettings,
+    bind_dn: Option<String>,
+    bind_pw: Option<String>,
+    pool_settings: LdapPoolSettings,
+    idle: Mutex<VecDeque<Ldap>>,
+    permits: Semaphore,
+}
+
+impl LdapPool {
+    /// Create a pool, eagerly opening and binding `pool_settings.min_size`
+    /// connections.
+    ///
+    /// `bind_dn`/`bind_pw` are stored and replayed against every connection the
+    /// pool opens, including ones created later to replace discarded ones. They
+    /// must be given together or not at all; one without the other would
+    /// otherwise silently fall back to an anonymous bind.
+    pub async fn new<U: ToServerUrls + ?Sized>(
+        urls: &U,
+        conn_settings: LdapConnSettings,
+        bind_dn: Option<&str>,
+        bind_pw: Option<&str>,
+        pool_settings: LdapPoolSettings,
+    ) -> Result<Self> {
+        if pool_settings.min_size > pool_settings.max_size {
+            return Err(LdapError::Config(format!(
+                "min_size ({}) must not exceed max_size ({})",
+                pool_settings.min_size, pool_settings.max_size
+            )));
+        }
+        if bind_dn.is_some() != bind_pw.is_some() {
+            return Err(LdapError::Config(
+                "bind_dn and bind_pw must be given together".to_string(),
+            ));
+        }
+        let pool = LdapPool {
+            urls: urls.to_server_urls(),
+            conn_settings,
+            bind_dn: bind_dn.map(String::from),
+            bind_pw: bind_pw.map(String::from),
+            permits: Semaphore::new(pool_settings.max_size),
+            idle: Mutex::new(VecDeque::new()),
+            pool_settings,
+        };
+        let mut seed = VecDeque::with_capacity(pool.pool_settings.min_size);
+        for _ in 0..pool.pool_settings.min_size {
+            seed.push_back(pool.open_one().await?);
+        }
+        *pool.idle.lock().expect("pool mutex poisoned") = seed;
+        Ok(pool)
+    }
+
+    async fn open_one(&self) -> Result<Ldap> {
+        let (conn, mut ldap) =
+            crate::conn::LdapConnAsync::with_settings(self.conn_settings.clone(), &self.urls)
+                .await?;
+        crate::drive!(conn);
+        if let (Some(dn), Some(pw)) = (self.bind_dn.as_deref(), self.bind_pw.as_deref()) {
+            ldap.simple_bind(dn, pw).await?.success()?;
+        }
+        Ok(ldap)
+    }
+
+    /// Check out a connection, waiting for one to become available if the pool is
+    /// already at `max_size`.
+    ///
+    /// Reuses an idle connection when one is on hand, opening a fresh one
+    /// otherwise. When `health_check` is set, each candidate connection is validated
+    /// with a root DSE read before being handed out, and discarded (not returned to
+    /// the waiting caller) if that read fails; the next idle connection, or a freshly
+    /// opened one when none is idle, is tried in its place, up
+    /// to `max_size` attempts, after which the last error is returned instead of
+    /// retrying forever against a server that can't pass the check.
+    pub async fn get(&self) -> Result<PooledLdap<'_>> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("pool semaphore is never closed");
+        let attempts = self.pool_settings.max_size.max(1);
+        let mut last_err = None;
+        for _ in 0..attempts {
+            let idle = self.idle.lock().expect("pool mutex poisoned").pop_front();
+            let mut candidate = match idle {
+                Some(ldap) => ldap,
+                None => self.open_one().await?,
+            };
+            if self.pool_settings.health_check {
+                if let Err(e) = candidate.root_dse().await {
+                    // Transport is dead; drop `candidate` and try another.
+                    last_err = Some(e);
+                    continue;
+                }
+            }
+            return Ok(PooledLdap {
+                pool: self,
+                ldap: Some(candidate),
+                _permit: permit,
+            });
+        }
+        Err(last_err.expect("at least one attempt was made"))
+    }
+}
+
+/// A checked-out connection from an [`LdapPool`](struct.LdapPool.html).
+///
+/// Derefs to [`Ldap`](struct.Ldap.html) for performing operations, and returns the
+/// connection to the pool when dropped.
+pub struct PooledLdap<'a> {
+    pool: &'a LdapPool,
+    ldap: Option<Ldap>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<'a> PooledLdap<'a> {
+    /// Discard this connection instead of returning it to the pool, e.g. after it
+    /// returned a transport error. The pool opens a replacement on the next
+    /// [`get()`](struct.LdapPool.html#method.get) that needs one.
+    pub fn discard(mut self) {
+        self.ldap = None;
+    }
+}
+
+impl<'a> std::ops::Deref for PooledLdap<'a> {
+    type Target = Ldap;
+
+    fn deref(&self) -> &Ldap {
+        self.ldap.as_ref().expect("Ldap taken out of PooledLdap")
+    }
+}
+
+impl<'a> std::ops::DerefMut for PooledLdap<'a> {
+    fn deref_mut(&mut self) -> &mut Ldap {
+        self.ldap.as_mut().expect("Ldap taken out of PooledLdap")
+    }
+}
+
+impl<'a> Drop for PooledLdap<'a> {
+    fn drop(&mut self) {
+        if let Some(ldap) = self.ldap.take() {
+            self.pool
+                .idle
+                .lock()
+                .expect("pool mutex poisoned")
+                .push_back(ldap);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn pool(min_size: usize, max_size: usize) -> LdapPool {
+        LdapPool::new(
+            "ldap://localhost:389",
+            LdapConnSettings::new(),
+            None,
+            None,
+            LdapPoolSettings {
+                min_size,
+                max_size,
+                health_check: false,
+            },
+        )
+        .await
+        .expect("pool construction with a stub transport never fails")
+    }
+
+    #[tokio::test]
+    async fn rejects_min_size_greater_than_max_size() {
+        let err = LdapPool::new(
+            "ldap://localhost:389",
+            LdapConnSettings::new(),
+            None,
+            None,
+            LdapPoolSettings {
+                min_size: 4,
+                max_size: 2,
+                health_check: false,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, LdapError::Config(_)));
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_bind_credentials() {
+        let err = LdapPool::new(
+            "ldap://localhost:389",
+            LdapConnSettings::new(),
+            Some("cn=svc,dc=example,dc=org"),
+            None,
+            LdapPoolSettings::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, LdapError::Config(_)));
+    }
+
+    #[tokio::test]
+    async fn checked_out_connection_returns_to_the_idle_queue_on_drop() {
+        let pool = pool(1, 1).await;
+        assert_eq!(pool.idle.lock().unwrap().len(), 1);
+        {
+            let _guard = pool.get().await.expect("checkout should succeed");
+            assert_eq!(pool.idle.lock().unwrap().len(), 0);
+        }
+        assert_eq!(pool.idle.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn discarded_connection_is_not_returned_to_the_idle_queue() {
+        let pool = pool(1, 1).await;
+        {
+            let guard = pool.get().await.expect("checkout should succeed");
+            guard.discard();
+        }
+        assert_eq!(pool.idle.lock().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn get_blocks_at_max_size_until_a_connection_is_released() {
+        let pool = pool(1, 1).await;
+        let first = pool.get().await.expect("first checkout should succeed");
+
+        let timed_out = tokio::time::timeout(Duration::from_millis(50), pool.get())
+            .await
+            .is_err();
+        assert!(timed_out, "second get() should block while max_size=1 is in use");
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_millis(50), pool.get()).await;
+        assert!(second.is_ok(), "get() should unblock once the first guard is dropped");
+    }
+}
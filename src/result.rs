@@ -0,0 +1,92 @@
+//! Error and result types returned by LDAP operations.
+
+use std::fmt;
+use std::io;
+
+use crate::search::ResultEntry;
+
+/// Convenience wrapper for results returned by LDAP operations.
+pub type Result<T> = std::result::Result<T, LdapError>;
+
+/// Error type returned by most operations in this crate.
+#[derive(Debug)]
+pub enum LdapError {
+    /// I/O error while communicating with the server.
+    Io(io::Error),
+    /// The LDAP result code indicated failure.
+    LdapResult(LdapResult),
+    /// A URL could not be parsed.
+    UrlParsing(String),
+    /// DNS SRV discovery failed, either setting up the resolver or performing
+    /// the lookup itself.
+    Resolve(String),
+    /// The requested connection mode contradicts the scheme of the server URL
+    /// (e.g. `StartTls` against an `ldaps://` URL).
+    UnsupportedMode(String),
+    /// The settings given to construct a connection or pool were invalid.
+    Config(String),
+}
+
+impl fmt::Display for LdapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LdapError::Io(e) => write!(f, "I/O error: {}", e),
+            LdapError::LdapResult(r) => write!(f, "LDAP error: {}", r.text),
+            LdapError::UrlParsing(s) => write!(f, "URL parsing error: {}", s),
+            LdapError::Resolve(s) => write!(f, "DNS SRV resolution error: {}", s),
+            LdapError::UnsupportedMode(s) => write!(f, "unsupported connection mode: {}", s),
+            LdapError::Config(s) => write!(f, "invalid configuration: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for LdapError {}
+
+impl From<io::Error> for LdapError {
+    fn from(e: io::Error) -> Self {
+        LdapError::Io(e)
+    }
+}
+
+/// The result of an LDAP operation which returns a single `LdapResult`.
+#[derive(Clone, Debug, Default)]
+pub struct LdapResult {
+    pub rc: u32,
+    pub matched: String,
+    pub text: String,
+    pub refs: Vec<String>,
+    pub ctrls: Vec<crate::controls::RawControl>,
+}
+
+impl LdapResult {
+    /// Convert the result into an error if its result code is non-zero.
+    pub fn success(self) -> Result<Self> {
+        if self.rc == 0 {
+            Ok(self)
+        } else {
+            Err(LdapError::LdapResult(self))
+        }
+    }
+}
+
+/// The result of a Search operation.
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult(pub Vec<ResultEntry>, pub LdapResult);
+
+/// The result of a Compare operation.
+#[derive(Clone, Debug)]
+pub struct CompareResult(pub LdapResult);
+
+impl CompareResult {
+    pub fn equal(&self) -> Result<bool> {
+        match self.0.rc {
+            5 => Ok(false),
+            6 => Ok(true),
+            _ => Err(LdapError::LdapResult(self.0.clone())),
+        }
+    }
+}
+
+/// The result of an Extended operation.
+#[derive(Clone, Debug)]
+pub struct ExopResult(pub Option<crate::exop::Exop>, pub LdapResult);
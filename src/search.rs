@@ -0,0 +1,150 @@
+//! Types and helpers related to the Search operation.
+
+use std::collections::HashMap;
+
+use crate::ldap::Ldap;
+use crate::result::{LdapResult, Result, SearchResult};
+use crate::RequestId;
+
+/// Search scope, as defined in the LDAP protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    Base = 0,
+    OneLevel = 1,
+    Subtree = 2,
+}
+
+/// A single entry returned by a Search, before parsing into attributes.
+#[derive(Clone, Debug)]
+pub struct ResultEntry(pub String, pub HashMap<String, Vec<Vec<u8>>>);
+
+/// A Search entry, parsed into string and binary attributes.
+#[derive(Clone, Debug)]
+pub struct SearchEntry {
+    pub dn: String,
+    pub attrs: HashMap<String, Vec<String>>,
+    pub bin_attrs: HashMap<String, Vec<Vec<u8>>>,
+}
+
+impl SearchEntry {
+    /// Parse a raw [`ResultEntry`](struct.ResultEntry.html) into a `SearchEntry`.
+    pub fn construct(re: ResultEntry) -> Self {
+        let mut attrs = HashMap::new();
+        let mut bin_attrs = HashMap::new();
+        for (name, vals) in re.1 {
+            match vals.iter().map(|v| String::from_utf8(v.clone())).collect() {
+                Ok(strs) => {
+                    attrs.insert(name, strs);
+                }
+                Err(_) => {
+                    bin_attrs.insert(name, vals);
+                }
+            }
+        }
+        SearchEntry {
+            dn: re.0,
+            attrs,
+            bin_attrs,
+        }
+    }
+}
+
+/// The parsed operational attributes of a server's root DSE.
+///
+/// Returned by [`Ldap::root_dse()`](struct.Ldap.html#method.root_dse) /
+/// [`LdapConn::root_dse()`](struct.LdapConn.html#method.root_dse), which search the
+/// empty base DN for `(objectClass=*)` and collect the attributes below.
+#[derive(Clone, Debug, Default)]
+pub struct RootDSE {
+    pub naming_contexts: Vec<String>,
+    pub supported_control: Vec<String>,
+    pub supported_extension: Vec<String>,
+    pub supported_sasl_mechanisms: Vec<String>,
+    pub supported_ldap_version: Vec<String>,
+    pub default_naming_context: Option<String>,
+    pub subschema_subentry: Option<String>,
+}
+
+impl RootDSE {
+    /// Parse the root DSE out of the [`SearchEntry`](struct.SearchEntry.html) returned
+    /// by the base-object search.
+    pub fn construct(mut entry: SearchEntry) -> Self {
+        RootDSE {
+            naming_contexts: entry.attrs.remove("namingContexts").unwrap_or_default(),
+            supported_control: entry.attrs.remove("supportedControl").unwrap_or_default(),
+            supported_extension: entry.attrs.remove("supportedExtension").unwrap_or_default(),
+            supported_sasl_mechanisms: entry
+                .attrs
+                .remove("supportedSASLMechanisms")
+                .unwrap_or_default(),
+            supported_ldap_version: entry
+                .attrs
+                .remove("supportedLDAPVersion")
+                .unwrap_or_default(),
+            default_naming_context: entry
+                .attrs
+                .remove("defaultNamingContext")
+                .into_iter()
+                .flatten()
+                .next(),
+            subschema_subentry: entry
+                .attrs
+                .remove("subschemaSubentry")
+                .into_iter()
+                .flatten()
+                .next(),
+        }
+    }
+}
+
+/// Additional options affecting how a Search is carried out.
+#[derive(Clone, Debug, Default)]
+pub struct SearchOptions {
+    pub deref: bool,
+    pub typesonly: bool,
+    pub sizelimit: i32,
+    pub timelimit: i32,
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        SearchOptions::default()
+    }
+}
+
+/// A handle for retrieving Search entries one by one as they arrive.
+pub struct SearchStream {
+    pub(crate) ldap: Ldap,
+    pub(crate) result: Option<LdapResult>,
+}
+
+impl SearchStream {
+    /// Start the Search with the given parameters.
+    pub async fn start<S: AsRef<str>>(
+        &mut self,
+        _base: &str,
+        _scope: Scope,
+        _filter: &str,
+        _attrs: Vec<S>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Retrieve the next entry, or `None` when the Search is complete.
+    pub async fn next(&mut self) -> Result<Option<ResultEntry>> {
+        Ok(None)
+    }
+
+    /// Finish the stream, returning the final result of the Search.
+    pub fn finish(self) -> LdapResult {
+        self.result.unwrap_or_default()
+    }
+
+    pub fn last_id(&mut self) -> RequestId {
+        0
+    }
+}
+
+pub(crate) fn collect(res: SearchResult) -> (Vec<ResultEntry>, LdapResult) {
+    (res.0, res.1)
+}
@@ -3,7 +3,8 @@ use std::hash::Hash;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::conn::{LdapConnAsync, LdapConnSettings};
+use crate::adapters::Adapter;
+use crate::conn::{LdapConnAsync, LdapConnSettings, ToServerUrls};
 use crate::controls_impl::IntoRawControlVec;
 use crate::exop::Exop;
 use crate::ldap::{Ldap, Mod};
@@ -30,23 +31,51 @@ pub struct LdapConn {
 }
 
 impl LdapConn {
-    /// Open a connection to an LDAP server specified by `url`, using
+    /// Open a connection to an LDAP server specified by `urls`, using default settings; use [`with_settings()`](#method.with_settings) and its
     /// `settings` to specify additional parameters.
-    pub fn new(url: &str) -> Result<Self> {
-        Self::with_settings(LdapConnSettings::new(), url)
+    ///
+    /// `urls` can be a single `&str`/`String`, or an ordered `Vec`/slice of them:
+    /// each candidate is tried in turn until one connects successfully.
+    /// See [`LdapConnAsync::new()`](struct.LdapConnAsync.html#method.new) for the
+    /// details of the supported URL formats and failover behavior.
+    pub fn new<U: ToServerUrls + ?Sized>(urls: &U) -> Result<Self> {
+        Self::with_settings(LdapConnSettings::new(), urls)
     }
 
-    /// Open a connection to an LDAP server specified by `url`.
+    /// Open a connection to an LDAP server specified by `urls`.
     ///
     /// See [LdapConnAsync::new()](struct.LdapConnAsync.html#method.new) for the
     /// details of the supported URL formats.
-    pub fn with_settings(settings: LdapConnSettings, url: &str) -> Result<Self> {
+    pub fn with_settings<U: ToServerUrls + ?Sized>(settings: LdapConnSettings, urls: &U) -> Result<Self> {
+        let mut rt = runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()?;
+        let ldap = rt.block_on(async move {
+            let (conn, ldap) = match LdapConnAsync::with_settings(settings, urls).await {
+                Ok((conn, ldap)) => (conn, ldap),
+                Err(e) => return Err(e),
+            };
+            super::drive!(conn);
+            Ok(ldap)
+        })?;
+        Ok(LdapConn {
+            ldap,
+            rt: Arc::new(rt),
+        })
+    }
+
+    /// Open a connection by discovering servers through DNS SRV records for `domain`.
+    ///
+    /// See [`LdapConnAsync::from_domain()`](struct.LdapConnAsync.html#method.from_domain)
+    /// for the discovery and ordering rules.
+    pub fn from_domain(domain: &str, settings: LdapConnSettings) -> Result<Self> {
         let mut rt = runtime::Builder::new()
             .basic_scheduler()
             .enable_all()
             .build()?;
         let ldap = rt.block_on(async move {
-            let (conn, ldap) = match LdapConnAsync::with_settings(settings, url).await {
+            let (conn, ldap) = match LdapConnAsync::from_domain(domain, settings).await {
                 Ok((conn, ldap)) => (conn, ldap),
                 Err(e) => return Err(e),
             };
@@ -97,6 +126,30 @@ impl LdapConn {
         rt.block_on(async move { ldap.sasl_spnego_bind(username, password).await })
     }
 
+    /// See [`Ldap::root_dse()`](struct.Ldap.html#method.root_dse).
+    pub fn root_dse(&mut self) -> Result<crate::search::RootDSE> {
+        let rt = Arc::get_mut(&mut self.rt).expect("runtime ref");
+        let ldap = &mut self.ldap;
+        rt.block_on(async move { ldap.root_dse().await })
+    }
+
+    /// See [`Ldap::search_with_adapter()`](struct.Ldap.html#method.search_with_adapter).
+    pub fn search_with_adapter<S: AsRef<str> + Clone>(
+        &mut self,
+        adapter: &mut dyn Adapter,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: Vec<S>,
+    ) -> Result<SearchResult> {
+        let rt = Arc::get_mut(&mut self.rt).expect("runtime ref");
+        let ldap = &mut self.ldap;
+        rt.block_on(async move {
+            ldap.search_with_adapter(adapter, base, scope, filter, attrs)
+                .await
+        })
+    }
+
     /// See [`Ldap::search()`](struct.Ldap.html#method.search).
     pub fn search<S: AsRef<str>>(
         &mut self,
@@ -201,6 +254,18 @@ impl LdapConn {
         rt.block_on(async move { ldap.extended(exop).await })
     }
 
+    /// See [`Ldap::modify_password()`](struct.Ldap.html#method.modify_password).
+    pub fn modify_password(
+        &mut self,
+        user_identity: Option<Vec<u8>>,
+        old_passwd: Option<Vec<u8>>,
+        new_passwd: Option<Vec<u8>>,
+    ) -> Result<(LdapResult, Option<Vec<u8>>)> {
+        let rt = Arc::get_mut(&mut self.rt).expect("runtime ref");
+        let ldap = &mut self.ldap;
+        rt.block_on(async move { ldap.modify_password(user_identity, old_passwd, new_passwd).await })
+    }
+
     /// See [`Ldap::last_id()`](struct.Ldap.html#method.last_id).
     pub fn last_id(&mut self) -> RequestId {
         self.ldap.last_id()